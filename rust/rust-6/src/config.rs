@@ -0,0 +1,36 @@
+use anyhow::Context;
+use serde::Deserialize;
+
+/// Deployment-time settings loaded once at startup, so operators can
+/// prewarm the database and tune cache/rate-limit behaviour without a
+/// recompile.
+#[derive(Deserialize, Debug)]
+pub struct Config {
+    pub seed_cities: Vec<SeedCity>,
+    pub freshness_secs: i64,
+    pub rate_limit_capacity: f64,
+    pub rate_limit_refill_per_sec: f64,
+    /// Whether a trusted reverse proxy sits in front of this service and
+    /// sets `X-Forwarded-For` itself. When `false`, the header is treated as
+    /// attacker-controlled and `/weather` rate-limiting falls back to a
+    /// single bucket shared by every client.
+    pub trust_x_forwarded_for: bool,
+}
+
+/// A city to upsert into the `cities` table on boot, so it's resolvable
+/// without an initial geocoding round-trip.
+#[derive(Deserialize, Debug)]
+pub struct SeedCity {
+    pub name: String,
+    pub lat: f64,
+    pub long: f64,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, anyhow::Error> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file at {path}"))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse config file at {path}"))
+    }
+}
@@ -2,16 +2,32 @@ use anyhow::Context;
 use askama::Template;
 use async_trait::async_trait;
 use axum::{
-    extract::{FromRequestParts, Query, State},
+    extract::{FromRequestParts, Json, Query, Request, State},
     http::StatusCode,
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Router,
 };
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use dashmap::DashMap;
 use http::request::Parts;
-use serde::Deserialize;
-use sqlx::PgPool;
-use std::str::from_utf8;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use shuttle_runtime::SecretStore;
+use sqlx::{types::Json as SqlxJson, PgPool};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::interval;
+use utoipa::{OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+mod config;
+use config::Config;
+
+/// Path to the deployment config file, relative to the crate root.
+const CONFIG_PATH: &str = "config.toml";
 
 // Make our own error that wraps `anyhow::Error`.
 struct AppError(anyhow::Error);
@@ -38,6 +54,73 @@ where
     }
 }
 
+/// Shared application state, reached by every handler via the `State` extractor.
+#[derive(Clone)]
+struct AppState {
+    pool: PgPool,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    rate_limiter: RateLimiter,
+    weather_cache_freshness_secs: i64,
+    trust_x_forwarded_for: bool,
+}
+
+/// How long an issued JWT remains valid, in seconds.
+const TOKEN_TTL_SECS: u64 = 60 * 60;
+
+struct RateLimitBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// How long a bucket may sit untouched before `sweep_stale` reclaims it.
+const BUCKET_IDLE_TTL: Duration = Duration::from_secs(600);
+
+/// A per-client token-bucket limiter: each client's bucket refills at
+/// `refill_rate` tokens/sec up to `capacity`, and a request needs one token.
+#[derive(Clone)]
+struct RateLimiter {
+    buckets: Arc<DashMap<String, RateLimitBucket>>,
+    capacity: f64,
+    refill_rate: f64,
+}
+
+impl RateLimiter {
+    fn new(capacity: f64, refill_rate: f64) -> Self {
+        Self {
+            buckets: Arc::new(DashMap::new()),
+            capacity,
+            refill_rate,
+        }
+    }
+
+    /// Refills `key`'s bucket for the elapsed time and takes a token if one
+    /// is available, returning whether the request may proceed.
+    fn try_acquire(&self, key: &str) -> bool {
+        let mut bucket = self.buckets.entry(key.to_string()).or_insert_with(|| RateLimitBucket {
+            tokens: self.capacity,
+            last_refill: Instant::now(),
+        });
+
+        let elapsed = bucket.last_refill.elapsed().as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_rate).min(self.capacity);
+        bucket.last_refill = Instant::now();
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drops buckets that haven't been touched in `idle_ttl`, so the map doesn't grow without bound.
+    fn sweep_stale(&self, idle_ttl: Duration) {
+        self.buckets
+            .retain(|_, bucket| bucket.last_refill.elapsed() < idle_ttl);
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct GeoResponse {
     pub results: Vec<LatLong>,
@@ -49,7 +132,7 @@ pub struct LatLong {
     pub longitude: f64,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct WeatherResponse {
     pub latitude: f64,
     pub longitude: f64,
@@ -57,26 +140,36 @@ pub struct WeatherResponse {
     pub hourly: Hourly,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Hourly {
     pub time: Vec<String>,
     pub temperature_2m: Vec<f64>,
+    pub apparent_temperature: Vec<f64>,
+    pub relative_humidity_2m: Vec<f64>,
+    pub wind_speed_10m: Vec<f64>,
+    pub precipitation: Vec<f64>,
 }
 
-#[derive(Template, Deserialize, Debug)]
+#[derive(Template, Deserialize, Debug, ToSchema)]
 #[template(path = "weather.html")]
 pub struct WeatherDisplay {
     pub city: String,
     pub forecasts: Vec<Forecast>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct Forecast {
     pub date: String,
-    pub temperature: String,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    pub temp_mean: f64,
+    pub apparent_temp_mean: f64,
+    pub humidity_mean: f64,
+    pub wind_speed_mean: f64,
+    pub precipitation_total: f64,
 }
 
-#[derive(sqlx::FromRow, Deserialize, Debug)]
+#[derive(sqlx::FromRow, Deserialize, Debug, ToSchema)]
 pub struct City {
     pub name: String,
 }
@@ -99,7 +192,7 @@ async fn fetch_lat_long(city: &str) -> Result<LatLong, anyhow::Error> {
 
 async fn fetch_weather(lat_long: LatLong) -> Result<WeatherResponse, anyhow::Error> {
     let endpoint = format!(
-        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&hourly=temperature_2m",
+        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&hourly=temperature_2m,apparent_temperature,relative_humidity_2m,wind_speed_10m,precipitation&timezone=auto",
         lat_long.latitude, lat_long.longitude
     );
     let response = reqwest::get(&endpoint)
@@ -109,35 +202,131 @@ async fn fetch_weather(lat_long: LatLong) -> Result<WeatherResponse, anyhow::Err
     Ok(response)
 }
 
+#[derive(sqlx::FromRow)]
+struct CachedWeather {
+    payload: SqlxJson<WeatherResponse>,
+    fetched_at: DateTime<Utc>,
+}
+
+/// Returns the weather for `city`, serving a fresh `weather_cache` row when
+/// one exists and refreshing it from Open-Meteo otherwise.
+async fn get_weather_cached(
+    pool: &PgPool,
+    city: &str,
+    lat_long: LatLong,
+    freshness_secs: i64,
+) -> Result<WeatherResponse, anyhow::Error> {
+    let cached = sqlx::query_as::<_, CachedWeather>(
+        "SELECT payload, fetched_at FROM weather_cache WHERE city = $1",
+    )
+    .bind(city)
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(cached) = cached {
+        let is_fresh = Utc::now() - cached.fetched_at < ChronoDuration::seconds(freshness_secs);
+        if is_fresh {
+            return Ok(cached.payload.0);
+        }
+    }
+
+    let weather = fetch_weather(lat_long).await?;
+    sqlx::query(
+        "INSERT INTO weather_cache (city, payload, fetched_at) VALUES ($1, $2, $3)
+         ON CONFLICT (city) DO UPDATE SET payload = EXCLUDED.payload, fetched_at = EXCLUDED.fetched_at",
+    )
+    .bind(city)
+    .bind(SqlxJson(&weather))
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(weather)
+}
+
 #[derive(Template)]
 #[template(path = "index.html")]
 struct IndexTemplate;
 
+/// Renders the landing page.
+#[utoipa::path(get, path = "/", responses((status = 200, description = "Landing page")))]
 async fn index() -> IndexTemplate {
     IndexTemplate
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema, utoipa::IntoParams)]
 pub struct WeatherQuery {
     pub city: String,
 }
 
+/// The hourly readings that fell on a single calendar day, kept as parallel
+/// vectors so each variable can be aggregated independently.
+#[derive(Default)]
+struct DailyReadings {
+    temperatures: Vec<f64>,
+    apparent_temperatures: Vec<f64>,
+    humidity: Vec<f64>,
+    wind_speed: Vec<f64>,
+    precipitation: Vec<f64>,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
 impl WeatherDisplay {
     fn new(city: String, response: WeatherResponse) -> Self {
-        let display = WeatherDisplay {
-            city,
-            forecasts: response
-                .hourly
-                .time
-                .iter()
-                .zip(response.hourly.temperature_2m.iter())
-                .map(|(date, temperature)| Forecast {
-                    date: date.to_string(),
-                    temperature: temperature.to_string(),
-                })
-                .collect(),
-        };
-        display
+        // Group hourly readings by `YYYY-MM-DD` in a `BTreeMap`, zipped rather
+        // than indexed so a length mismatch truncates instead of panicking.
+        let mut by_day: BTreeMap<String, DailyReadings> = BTreeMap::new();
+        let hourly = &response.hourly;
+        let rows = hourly
+            .time
+            .iter()
+            .zip(hourly.temperature_2m.iter())
+            .zip(hourly.apparent_temperature.iter())
+            .zip(hourly.relative_humidity_2m.iter())
+            .zip(hourly.wind_speed_10m.iter())
+            .zip(hourly.precipitation.iter());
+        for (((((time, temperature), apparent_temperature), humidity), wind_speed), precipitation) in
+            rows
+        {
+            let date = time
+                .split('T')
+                .next()
+                .expect("time always has a date prefix")
+                .to_string();
+            let readings = by_day.entry(date).or_default();
+            readings.temperatures.push(*temperature);
+            readings.apparent_temperatures.push(*apparent_temperature);
+            readings.humidity.push(*humidity);
+            readings.wind_speed.push(*wind_speed);
+            readings.precipitation.push(*precipitation);
+        }
+
+        let forecasts = by_day
+            .into_iter()
+            .map(|(date, readings)| Forecast {
+                date,
+                temp_min: readings
+                    .temperatures
+                    .iter()
+                    .cloned()
+                    .fold(f64::INFINITY, f64::min),
+                temp_max: readings
+                    .temperatures
+                    .iter()
+                    .cloned()
+                    .fold(f64::NEG_INFINITY, f64::max),
+                temp_mean: mean(&readings.temperatures),
+                apparent_temp_mean: mean(&readings.apparent_temperatures),
+                humidity_mean: mean(&readings.humidity),
+                wind_speed_mean: mean(&readings.wind_speed),
+                precipitation_total: readings.precipitation.iter().sum(),
+            })
+            .collect();
+
+        WeatherDisplay { city, forecasts }
     }
 }
 
@@ -164,78 +353,514 @@ async fn get_lat_long(pool: &PgPool, name: &str) -> Result<LatLong, anyhow::Erro
     Ok(lat_long)
 }
 
+/// The bucket key used when there's no trustworthy per-client identity.
+const UNKNOWN_CLIENT_KEY: &str = "unknown";
+
+/// Throttles `/weather` via the shared `RateLimiter`, keyed by
+/// `X-Forwarded-For` only when `trust_x_forwarded_for` is set, since
+/// `shuttle_axum` doesn't expose a verified peer address otherwise.
+async fn rate_limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let key = if state.trust_x_forwarded_for {
+        request
+            .headers()
+            .get("X-Forwarded-For")
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.split(',').next())
+            .map(|ip| ip.trim().to_string())
+            .unwrap_or_else(|| UNKNOWN_CLIENT_KEY.to_string())
+    } else {
+        UNKNOWN_CLIENT_KEY.to_string()
+    };
+
+    if state.rate_limiter.try_acquire(&key) {
+        return next.run(request).await;
+    }
+
+    let retry_after = (1.0 / state.rate_limiter.refill_rate).ceil() as u64;
+    axum::http::Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header("Retry-After", retry_after.to_string())
+        .body(axum::body::Body::from("Too many requests"))
+        .unwrap()
+}
+
+/// Looks up the forecast for a city, geocoding and caching as needed.
+#[utoipa::path(
+    get,
+    path = "/weather",
+    params(WeatherQuery),
+    responses(
+        (status = 200, description = "Daily forecast", body = WeatherDisplay),
+        (status = 500, description = "Upstream or database error"),
+    )
+)]
 async fn weather(
     Query(params): Query<WeatherQuery>,
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
 ) -> Result<WeatherDisplay, AppError> {
-    let lat_long = get_lat_long(&pool, &params.city).await?;
-    let weather = fetch_weather(lat_long).await?;
+    let lat_long = get_lat_long(&state.pool, &params.city).await?;
+    let weather = get_weather_cached(
+        &state.pool,
+        &params.city,
+        lat_long,
+        state.weather_cache_freshness_secs,
+    )
+    .await?;
     Ok(WeatherDisplay::new(params.city, weather))
 }
 
-/// A user that is authorized to access the stats endpoint.
-///
-/// No fields are required, we just need to know that the user is authorized. In
-/// a production application you would probably want to have some kind of user
-/// ID or similar here.
-struct User;
+/// A row from the `users` table, whose `password_hash` is a bcrypt hash.
+#[derive(sqlx::FromRow, Debug)]
+struct UserRecord {
+    id: i64,
+    password_hash: String,
+}
+
+/// The claims embedded in a JWT issued by `/login`.
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    exp: usize,
+    scope: String,
+}
 
 #[async_trait]
-impl<S> FromRequestParts<S> for User
-where
-    S: Send + Sync,
-{
-    type Rejection = axum::http::Response<axum::body::Body>;
+impl FromRequestParts<AppState> for Claims {
+    type Rejection = Response;
 
-    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
-        let auth_header = parts
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let token = parts
             .headers
             .get("Authorization")
-            .and_then(|header| header.to_str().ok());
-
-        if let Some(auth_header) = auth_header {
-            if auth_header.starts_with("Basic ") {
-                let credentials = auth_header.trim_start_matches("Basic ");
-                let decoded = base64::decode(credentials).unwrap_or_default();
-                let credential_str = from_utf8(&decoded).unwrap_or("");
-
-                if credential_str == "forecast:forecast" {
-                    return Ok(User);
-                }
-            }
-        }
-
-        let reject_response = axum::http::Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header(
-                "WWW-Authenticate",
-                "Basic realm=\"Please enter your credentials\"",
-            )
-            .body(axum::body::Body::from("Unauthorized"))
-            .unwrap();
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .ok_or_else(|| unauthorized("Missing bearer token"))?;
+
+        let data = decode::<Claims>(
+            token,
+            &state.decoding_key,
+            &Validation::new(Algorithm::HS256),
+        )
+        .map_err(|_| unauthorized("Invalid or expired token"))?;
 
-        Err(reject_response)
+        Ok(data.claims)
     }
 }
 
+fn unauthorized(message: &str) -> Response {
+    axum::http::Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .body(axum::body::Body::from(message.to_string()))
+        .unwrap()
+}
+
+#[derive(Deserialize, ToSchema)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct LoginResponse {
+    token: String,
+}
+
+/// A bcrypt hash with no matching password, checked instead of a real one
+/// when `username` has no row, so a miss costs the same as a hit.
+fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        bcrypt::hash("dummy-password-for-constant-time-login", bcrypt::DEFAULT_COST)
+            .expect("hashing a fixed dummy password never fails")
+    })
+}
+
+/// Validates `username`/`password` against the `users` table and, on
+/// success, hands back an HS256-signed JWT scoped to `stats`.
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Issued bearer token", body = LoginResponse),
+        (status = 401, description = "Invalid username or password"),
+    )
+)]
+async fn login(
+    State(state): State<AppState>,
+    Json(credentials): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, Response> {
+    let user = sqlx::query_as::<_, UserRecord>(
+        "SELECT id, password_hash FROM users WHERE username = $1",
+    )
+    .bind(&credentials.username)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|_| unauthorized("Invalid username or password"))?;
+
+    // Always run `bcrypt::verify`, even on a miss, so both paths cost the same.
+    let password_hash = user
+        .as_ref()
+        .map(|user| user.password_hash.as_str())
+        .unwrap_or_else(dummy_password_hash);
+    let valid = bcrypt::verify(&credentials.password, password_hash).unwrap_or(false);
+
+    let user = match (user, valid) {
+        (Some(user), true) => user,
+        _ => return Err(unauthorized("Invalid username or password")),
+    };
+
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| unauthorized("Failed to issue token"))?
+        .as_secs()
+        + TOKEN_TTL_SECS;
+
+    let claims = Claims {
+        sub: user.id.to_string(),
+        exp: exp as usize,
+        scope: "stats".to_string(),
+    };
+
+    let token = encode(&Header::new(Algorithm::HS256), &claims, &state.encoding_key)
+        .map_err(|_| unauthorized("Failed to issue token"))?;
+
+    Ok(Json(LoginResponse { token }))
+}
+
 #[derive(Template)]
 #[template(path = "stats.html")]
 struct StatsTemplate {
     pub cities: Vec<City>,
 }
 
-async fn stats(_user: User, State(pool): State<PgPool>) -> Result<StatsTemplate, AppError> {
-    let cities = get_last_cities(&pool).await?;
-    Ok(StatsTemplate { cities })
+/// Lists the most recently looked-up cities. Requires a `stats`-scoped
+/// bearer token obtained from `/login`.
+#[utoipa::path(
+    get,
+    path = "/stats",
+    security(("bearer_token" = [])),
+    responses(
+        (status = 200, description = "Recently looked-up cities", body = [City]),
+        (status = 401, description = "Missing, invalid or under-scoped token"),
+        (status = 500, description = "Database error"),
+    )
+)]
+async fn stats(claims: Claims, State(state): State<AppState>) -> Response {
+    if claims.scope != "stats" {
+        return unauthorized("Insufficient scope");
+    }
+
+    match get_last_cities(&state.pool).await {
+        Ok(cities) => StatsTemplate { cities }.into_response(),
+        Err(err) => AppError(err).into_response(),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(index, weather, login, stats),
+    components(schemas(
+        WeatherDisplay,
+        Forecast,
+        City,
+        WeatherQuery,
+        LoginRequest,
+        LoginResponse
+    )),
+    modifiers(&SecurityAddon)
+)]
+struct ApiDoc;
+
+struct SecurityAddon;
+
+impl utoipa::Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+        let components = openapi.components.as_mut().expect("components registered above");
+        components.add_security_scheme(
+            "bearer_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
 }
 
 #[shuttle_runtime::main]
-async fn main(#[shuttle_aws_rds::Postgres] pool: PgPool) -> shuttle_axum::ShuttleAxum {
+async fn main(
+    #[shuttle_aws_rds::Postgres] pool: PgPool,
+    #[shuttle_runtime::Secrets] secrets: SecretStore,
+) -> shuttle_axum::ShuttleAxum {
+    let jwt_secret = secrets
+        .get("JWT_SECRET")
+        .context("JWT_SECRET not found in Shuttle secrets")?;
+
+    let config = Config::load(CONFIG_PATH).context("failed to load config")?;
+
+    if !config.trust_x_forwarded_for {
+        tracing::warn!(
+            "trust_x_forwarded_for is false: /weather rate-limiting shares one bucket across all clients"
+        );
+    }
+
+    for seed_city in &config.seed_cities {
+        let existing = sqlx::query_as::<_, LatLong>(
+            "SELECT lat AS latitude, long AS longitude FROM cities WHERE name = $1",
+        )
+        .bind(&seed_city.name)
+        .fetch_optional(&pool)
+        .await?;
+
+        if existing.is_some() {
+            sqlx::query("UPDATE cities SET lat = $2, long = $3 WHERE name = $1")
+                .bind(&seed_city.name)
+                .bind(seed_city.lat)
+                .bind(seed_city.long)
+                .execute(&pool)
+                .await?;
+            continue;
+        }
+
+        sqlx::query("INSERT INTO cities (name, lat, long) VALUES ($1, $2, $3)")
+            .bind(&seed_city.name)
+            .bind(seed_city.lat)
+            .bind(seed_city.long)
+            .execute(&pool)
+            .await?;
+    }
+
+    let state = AppState {
+        pool,
+        encoding_key: EncodingKey::from_secret(jwt_secret.as_bytes()),
+        decoding_key: DecodingKey::from_secret(jwt_secret.as_bytes()),
+        rate_limiter: RateLimiter::new(
+            config.rate_limit_capacity,
+            config.rate_limit_refill_per_sec,
+        ),
+        weather_cache_freshness_secs: config.freshness_secs,
+        trust_x_forwarded_for: config.trust_x_forwarded_for,
+    };
+
+    let sweep_limiter = state.rate_limiter.clone();
+    tokio::spawn(async move {
+        let mut ticker = interval(BUCKET_IDLE_TTL);
+        loop {
+            ticker.tick().await;
+            sweep_limiter.sweep_stale(BUCKET_IDLE_TTL);
+        }
+    });
+
     let router = Router::new()
         .route("/", get(index))
-        .route("/weather", get(weather))
+        .route(
+            "/weather",
+            get(weather).route_layer(middleware::from_fn_with_state(state.clone(), rate_limit)),
+        )
+        .route("/login", post(login))
         .route("/stats", get(stats))
-        .with_state(pool);
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .with_state(state);
 
     Ok(router.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_round_trip_through_encode_and_decode() {
+        let encoding_key = EncodingKey::from_secret(b"test-secret");
+        let decoding_key = DecodingKey::from_secret(b"test-secret");
+
+        let claims = Claims {
+            sub: "42".to_string(),
+            exp: usize::MAX,
+            scope: "stats".to_string(),
+        };
+
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &encoding_key)
+            .expect("encoding with a valid key succeeds");
+        let decoded = decode::<Claims>(&token, &decoding_key, &Validation::new(Algorithm::HS256))
+            .expect("decoding a token signed with the matching key succeeds");
+
+        assert_eq!(decoded.claims.sub, claims.sub);
+        assert_eq!(decoded.claims.scope, claims.scope);
+        assert_eq!(decoded.claims.exp, claims.exp);
+    }
+
+    #[test]
+    fn decode_rejects_token_signed_with_a_different_key() {
+        let encoding_key = EncodingKey::from_secret(b"test-secret");
+        let wrong_decoding_key = DecodingKey::from_secret(b"not-the-same-secret");
+
+        let claims = Claims {
+            sub: "42".to_string(),
+            exp: usize::MAX,
+            scope: "stats".to_string(),
+        };
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &encoding_key)
+            .expect("encoding with a valid key succeeds");
+
+        let result = decode::<Claims>(
+            &token,
+            &wrong_decoding_key,
+            &Validation::new(Algorithm::HS256),
+        );
+
+        assert!(result.is_err());
+    }
+
+    fn hourly_reading(
+        time: &str,
+        temperature: f64,
+        apparent_temperature: f64,
+        humidity: f64,
+        wind_speed: f64,
+        precipitation: f64,
+    ) -> (String, f64, f64, f64, f64, f64) {
+        (
+            time.to_string(),
+            temperature,
+            apparent_temperature,
+            humidity,
+            wind_speed,
+            precipitation,
+        )
+    }
+
+    fn weather_response_from(readings: Vec<(String, f64, f64, f64, f64, f64)>) -> WeatherResponse {
+        let mut hourly = Hourly {
+            time: Vec::new(),
+            temperature_2m: Vec::new(),
+            apparent_temperature: Vec::new(),
+            relative_humidity_2m: Vec::new(),
+            wind_speed_10m: Vec::new(),
+            precipitation: Vec::new(),
+        };
+        for (time, temperature, apparent_temperature, humidity, wind_speed, precipitation) in readings
+        {
+            hourly.time.push(time);
+            hourly.temperature_2m.push(temperature);
+            hourly.apparent_temperature.push(apparent_temperature);
+            hourly.relative_humidity_2m.push(humidity);
+            hourly.wind_speed_10m.push(wind_speed);
+            hourly.precipitation.push(precipitation);
+        }
+        WeatherResponse {
+            latitude: 0.0,
+            longitude: 0.0,
+            timezone: "UTC".to_string(),
+            hourly,
+        }
+    }
+
+    #[test]
+    fn weather_display_buckets_by_day_and_aggregates_each_day() {
+        let response = weather_response_from(vec![
+            hourly_reading("2024-01-01T00:00", 0.0, -2.0, 80.0, 5.0, 0.0),
+            hourly_reading("2024-01-01T12:00", 10.0, 8.0, 40.0, 15.0, 1.0),
+            hourly_reading("2024-01-02T00:00", 20.0, 18.0, 60.0, 10.0, 2.0),
+        ]);
+
+        let display = WeatherDisplay::new("Berlin".to_string(), response);
+
+        assert_eq!(display.city, "Berlin");
+        assert_eq!(display.forecasts.len(), 2);
+
+        let day_one = &display.forecasts[0];
+        assert_eq!(day_one.date, "2024-01-01");
+        assert_eq!(day_one.temp_min, 0.0);
+        assert_eq!(day_one.temp_max, 10.0);
+        assert_eq!(day_one.temp_mean, 5.0);
+        assert_eq!(day_one.apparent_temp_mean, 3.0);
+        assert_eq!(day_one.humidity_mean, 60.0);
+        assert_eq!(day_one.wind_speed_mean, 10.0);
+        assert_eq!(day_one.precipitation_total, 1.0);
+
+        let day_two = &display.forecasts[1];
+        assert_eq!(day_two.date, "2024-01-02");
+        assert_eq!(day_two.temp_min, 20.0);
+        assert_eq!(day_two.temp_max, 20.0);
+        assert_eq!(day_two.temp_mean, 20.0);
+    }
+
+    #[test]
+    fn weather_display_stops_at_the_shortest_array_on_length_mismatch() {
+        // Open-Meteo is expected to return parallel arrays of equal length,
+        // but `temperature_2m` here is one entry short. `WeatherDisplay::new`
+        // should zip down to the shortest array instead of panicking on an
+        // out-of-bounds index.
+        let hourly = Hourly {
+            time: vec!["2024-01-01T00:00".to_string(), "2024-01-01T12:00".to_string()],
+            temperature_2m: vec![0.0],
+            apparent_temperature: vec![-2.0, 8.0],
+            relative_humidity_2m: vec![80.0, 40.0],
+            wind_speed_10m: vec![5.0, 15.0],
+            precipitation: vec![0.0, 1.0],
+        };
+        let response = WeatherResponse {
+            latitude: 0.0,
+            longitude: 0.0,
+            timezone: "UTC".to_string(),
+            hourly,
+        };
+
+        let display = WeatherDisplay::new("Berlin".to_string(), response);
+
+        assert_eq!(display.forecasts.len(), 1);
+        let day = &display.forecasts[0];
+        assert_eq!(day.temp_min, 0.0);
+        assert_eq!(day.temp_max, 0.0);
+    }
+
+    #[test]
+    fn rate_limiter_exhausts_and_refills_over_time() {
+        let limiter = RateLimiter::new(2.0, 1.0);
+
+        assert!(limiter.try_acquire("client"));
+        assert!(limiter.try_acquire("client"));
+        assert!(!limiter.try_acquire("client"), "bucket should be empty after 2 tokens");
+
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(
+            limiter.try_acquire("client"),
+            "a refill tick later should have replenished at least one token"
+        );
+    }
+
+    #[test]
+    fn rate_limiter_keys_are_independent() {
+        let limiter = RateLimiter::new(1.0, 1.0);
+
+        assert!(limiter.try_acquire("a"));
+        assert!(!limiter.try_acquire("a"));
+        assert!(
+            limiter.try_acquire("b"),
+            "a different key should get its own bucket"
+        );
+    }
+
+    #[test]
+    fn sweep_stale_evicts_only_idle_buckets() {
+        let limiter = RateLimiter::new(1.0, 1.0);
+        limiter.try_acquire("stale");
+        limiter.try_acquire("fresh");
+
+        limiter.sweep_stale(Duration::from_millis(0));
+        assert_eq!(limiter.buckets.len(), 0, "a zero TTL should evict every bucket");
+
+        limiter.try_acquire("fresh");
+        limiter.sweep_stale(Duration::from_secs(600));
+        assert_eq!(
+            limiter.buckets.len(),
+            1,
+            "a just-touched bucket should survive a long-lived TTL"
+        );
+    }
+}